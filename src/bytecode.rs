@@ -0,0 +1,162 @@
+//! Flat bytecode compiled from a `Program` AST
+//!
+//! `compile` flattens a `Program` into a `Vec<Instr>` once, coalescing runs
+//! of pointer/data ops and the `[-]`/`[+]` idiom, so `Context::run` only
+//! needs a `pc` loop over a flat slice instead of re-walking the AST.
+
+use {Command, Program, Op, Loop};
+use {TapeConfig, TapeSize, PointerWrap, CellOverflow};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// A single bytecode instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
+    /// add to the current cell, applying the context's `CellOverflow` mode
+    AddData(i8),
+    /// move the data pointer by this many cells, applying the context's
+    /// `PointerWrap` mode on a fixed-size tape
+    MovePtr(isize),
+    /// set the current cell to zero; compiled from the `[-]`/`[+]` idiom
+    SetZero,
+    GetByte,
+    PutByte,
+    /// `#`: dump the current tape window and pointer
+    DumpTape,
+    /// `!`: breakpoint; a no-op under `Context::run`
+    Breakpoint,
+    /// jump to the given instruction index if the current cell is zero
+    JumpIfZero(usize),
+    /// jump to the given instruction index if the current cell is non-zero
+    JumpIfNonZero(usize),
+}
+
+/// Flat, compiled form of a `Program`, ready for `Context::run`
+#[derive(Debug, Clone)]
+pub struct Bytecode {
+    instrs: Vec<Instr>,
+}
+
+impl Bytecode {
+    /// the compiled instruction stream
+    pub fn instrs(&self) -> &[Instr] {
+        &self.instrs
+    }
+}
+
+/// Compile a `Program` AST into flat `Bytecode`
+pub trait Compile {
+    /// compile with the default `TapeConfig` (unbounded tape, wrapping
+    /// pointer and cell arithmetic), under which every coalescing below is
+    /// sound
+    fn compile(&self) -> Bytecode;
+
+    /// compile for a specific `TapeConfig`, disabling whichever coalescing
+    /// would change behaviour under that config's `PointerWrap`/
+    /// `CellOverflow` — see `compile_into`
+    fn compile_for(&self, config: TapeConfig) -> Bytecode;
+}
+
+impl Compile for Program {
+    fn compile(&self) -> Bytecode {
+        self.compile_for(TapeConfig::default())
+    }
+
+    fn compile_for(&self, config: TapeConfig) -> Bytecode {
+        let mut instrs = Vec::with_capacity(self.len());
+        compile_into(self, &mut instrs, config);
+        Bytecode { instrs: instrs }
+    }
+}
+
+/// coalescing runs of `MovePtr`/`AddData` into one instruction only produces
+/// the same result as running each op individually when the combined effect
+/// is applied once at the end — true for modular (wrapping) arithmetic, but
+/// not for `PointerWrap::Clamp`/`Error` or `CellOverflow::Saturating`, which
+/// can see and react to an intermediate boundary crossing that a coalesced
+/// run skips right over. `config` says which of those are safe here.
+fn compile_into(p: &Program, out: &mut Vec<Instr>, config: TapeConfig) {
+    let coalesce_pointer = match config.size {
+        TapeSize::Unbounded => true,
+        TapeSize::Fixed(_) => config.pointer_wrap == PointerWrap::WrapAround,
+    };
+    let coalesce_data = config.overflow == CellOverflow::Wrapping;
+
+    let mut i = 0;
+    while i < p.len() {
+        match p[i] {
+            Op(Command::IncPointer) | Op(Command::DecPointer) => {
+                if coalesce_pointer {
+                    let mut delta: isize = 0;
+                    while i < p.len() {
+                        match p[i] {
+                            Op(Command::IncPointer) => { delta = delta.wrapping_add(1); i += 1 },
+                            Op(Command::DecPointer) => { delta = delta.wrapping_sub(1); i += 1 },
+                            _ => break,
+                        }
+                    }
+                    out.push(Instr::MovePtr(delta));
+                } else {
+                    out.push(match p[i] {
+                        Op(Command::IncPointer) => Instr::MovePtr(1),
+                        _ => Instr::MovePtr(-1),
+                    });
+                    i += 1;
+                }
+            },
+            Op(Command::IncData) | Op(Command::DecData) => {
+                if coalesce_data {
+                    let mut delta: i32 = 0;
+                    while i < p.len() {
+                        match p[i] {
+                            Op(Command::IncData) => { delta = delta.wrapping_add(1); i += 1 },
+                            Op(Command::DecData) => { delta = delta.wrapping_sub(1); i += 1 },
+                            _ => break,
+                        }
+                    }
+                    out.push(Instr::AddData(delta as i8));
+                } else {
+                    out.push(match p[i] {
+                        Op(Command::IncData) => Instr::AddData(1),
+                        _ => Instr::AddData(-1),
+                    });
+                    i += 1;
+                }
+            },
+            Op(Command::GetByte) => { out.push(Instr::GetByte); i += 1 },
+            Op(Command::PutByte) => { out.push(Instr::PutByte); i += 1 },
+            Op(Command::DumpTape) => { out.push(Instr::DumpTape); i += 1 },
+            Op(Command::Breakpoint) => { out.push(Instr::Breakpoint); i += 1 },
+            Loop(ref body) => {
+                if is_zeroing_loop(body, config.overflow) {
+                    out.push(Instr::SetZero);
+                } else {
+                    // push a placeholder JumpIfZero, patch it once we know
+                    // where the matching close ends up
+                    let open = out.len();
+                    out.push(Instr::JumpIfZero(0));
+                    compile_into(body, out, config);
+                    let close = out.len();
+                    out.push(Instr::JumpIfNonZero(open + 1));
+                    out[open] = Instr::JumpIfZero(close + 1);
+                }
+                i += 1;
+            },
+        }
+    }
+}
+
+/// recognize the `[-]`/`[+]` idiom: a loop whose entire body is a single
+/// data decrement always just zeroes the current cell; a single increment
+/// only does under wrapping arithmetic — under `CellOverflow::Saturating` it
+/// sticks at 255 and never reaches zero unless it started there
+fn is_zeroing_loop(body: &Program, overflow: CellOverflow) -> bool {
+    if body.len() != 1 {
+        return false;
+    }
+    match body[0] {
+        Op(Command::DecData) => true,
+        Op(Command::IncData) => overflow == CellOverflow::Wrapping,
+        _ => false,
+    }
+}
@@ -0,0 +1,16 @@
+//! Minimal stand-ins for `std::io::Read`/`Write`, used when the `std`
+//! feature is disabled
+//!
+//! Mirror just the two methods `Context` actually needs, so the rest of the
+//! crate doesn't care whether it's linked against real `std::io` or this.
+
+/// Analogous to `std::io::Read::read`: returns the number of bytes read,
+/// with `Ok(0)` signalling EOF
+pub trait Read {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, &'static str>;
+}
+
+/// Analogous to `std::io::Write::write_all`
+pub trait Write {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), &'static str>;
+}
@@ -1,11 +1,45 @@
-/// Library to parse and execute brainfuck programs
+//! Library to parse and execute brainfuck programs
+//!
+//! Builds `no_std` when the default `std` feature is disabled: the parser
+//! only ever needed an `Iterator<Item=char>`, and the interpreter's I/O goes
+//! through the `Read`/`Write` shim in `io_nostd` instead of `std::io`.
+#![cfg_attr(not(feature = "std"), no_std)]
+// this crate targets edition 2015 and uses `try!` and `field: field` struct
+// init throughout on purpose, predating `?` and field-init shorthand
+#![allow(deprecated)]
+#![allow(clippy::redundant_field_names)]
 
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+mod io_nostd;
+mod bytecode;
+mod tape;
+mod position;
+
+#[cfg(feature = "std")]
 use std::error::Error;
+#[cfg(feature = "std")]
 use std::fmt;
-use std::io::stdin;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{Read, Write, Stdin, Stdout, stdin, stdout};
+#[cfg(not(feature = "std"))]
+use io_nostd::{Read, Write};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
 
 pub use self::Command::*;
 pub use self::Ast::*;
+pub use self::bytecode::{Instr, Bytecode, Compile};
+pub use self::tape::{TapeConfig, TapeSize, PointerWrap, CellOverflow};
+pub use self::position::Position;
 
 /// Brainfuck command
 #[derive(Debug)]
@@ -16,6 +50,13 @@ pub enum Command {
     DecData,
     GetByte,
     PutByte,
+    /// `#`: dump the current tape window and pointer; only parsed when
+    /// `ParseConfig::extensions` is enabled
+    DumpTape,
+    /// `!`: mark a breakpoint; only parsed when `ParseConfig::extensions` is
+    /// enabled. Has no effect under `execute`/`run` — it exists for
+    /// `execute_traced`'s hook to act on
+    Breakpoint,
 }
 
 /// Node in a brainfuck AST
@@ -28,31 +69,76 @@ pub enum Ast {
 /// A brainfuck program is just a list of AST nodes
 pub type Program = Vec<Ast>;
 
+/// Policy for what a `GetByte` should do to the current data cell when the
+/// input stream has reached EOF, since this is left undefined by the
+/// brainfuck spec and implementations disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EofPolicy {
+    /// leave the current cell unchanged
+    Unchanged,
+    /// write a zero byte into the current cell
+    Zero,
+    /// write 0xFF into the current cell
+    Max,
+}
+
 /// Context in which a brainfuck program executes
+///
+/// Input and output are generic over `Read`/`Write` so a program can be
+/// driven from anything: stdin/stdout via `Context::stdio`, an in-memory
+/// buffer for tests, a `TcpStream`, and so on.
 #[derive(Debug)]
-pub struct Context {
+pub struct Context<R: Read, W: Write> {
     dp: usize,
     data: Vec<u8>,
+    input: R,
+    output: W,
+    eof_policy: EofPolicy,
+    tape_size: TapeSize,
+    pointer_wrap: PointerWrap,
+    overflow: CellOverflow,
 }
 
-impl Context {
-    /// build a new program context initialized with all zeroes
-    pub fn new() -> Context {
-        Context { dp: 0, data: Vec::with_capacity(100) }
+impl<R: Read, W: Write> Context<R, W> {
+    /// build a new program context initialized with all zeroes, reading from
+    /// `input` and writing to `output`
+    ///
+    /// EOF reads leave the current cell unchanged and the tape is unbounded;
+    /// use `with_eof_policy` or `with_config` to pick different behaviour.
+    pub fn new(input: R, output: W) -> Context<R, W> {
+        Context::with_eof_policy(input, output, EofPolicy::Unchanged)
+    }
+
+    /// build a new program context with an explicit `EofPolicy` and the
+    /// default (unbounded, wrapping) tape
+    pub fn with_eof_policy(input: R, output: W, eof_policy: EofPolicy) -> Context<R, W> {
+        Context::with_config(input, output, eof_policy, TapeConfig::default())
+    }
+
+    /// build a new program context with an explicit `EofPolicy` and `TapeConfig`
+    pub fn with_config(input: R, output: W, eof_policy: EofPolicy, tape: TapeConfig) -> Context<R, W> {
+        let data = match tape.size {
+            TapeSize::Unbounded => Vec::with_capacity(100),
+            TapeSize::Fixed(n) => vec![0u8; n],
+        };
+        Context {
+            dp: 0,
+            data: data,
+            input: input,
+            output: output,
+            eof_policy: eof_policy,
+            tape_size: tape.size,
+            pointer_wrap: tape.pointer_wrap,
+            overflow: tape.overflow,
+        }
     }
 
     /// execute program `p` in this context
     pub fn execute(&mut self, p: &Program) -> Result<(), String> {
         for node in p {
-            let cur_data = self.cur_data();
             match *node {
-                Op(IncPointer) => self.dp = self.dp.wrapping_add(1),
-                Op(DecPointer) => self.dp = self.dp.wrapping_sub(1),
-                Op(IncData)    => self.set_cur_data(cur_data.wrapping_add(1)),
-                Op(DecData)    => self.set_cur_data(cur_data.wrapping_sub(1)),
-                Op(GetByte)    => self.set_cur_data(try!(Context::getbyte())),
-                Op(PutByte)    => print!("{:}", self.cur_data() as char),
-                Loop(ref x)    =>  {
+                Op(ref cmd) => try!(self.execute_op(cmd)),
+                Loop(ref x) =>  {
                     while self.getdata(self.dp) != 0 {
                         try!(self.execute(x))
                     }
@@ -62,20 +148,168 @@ impl Context {
         Ok(())
     }
 
+    /// execute program `p`, calling `hook` with the command and context
+    /// just before every op runs
+    ///
+    /// This is `execute` with a stepping hook wired in, letting callers
+    /// implement single-step debugging, instruction counting, or animated
+    /// tape visualization; `Breakpoint` commands are otherwise no-ops, so
+    /// acting on them is entirely up to `hook`.
+    pub fn execute_traced<F>(&mut self, p: &Program, hook: &mut F) -> Result<(), String>
+        where F: FnMut(&Command, &Context<R, W>)
+    {
+        for node in p {
+            match *node {
+                Op(ref cmd) => {
+                    hook(cmd, &*self);
+                    try!(self.execute_op(cmd));
+                },
+                Loop(ref x) =>  {
+                    while self.getdata(self.dp) != 0 {
+                        try!(self.execute_traced(x, hook))
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn execute_op(&mut self, cmd: &Command) -> Result<(), String> {
+        let cur_data = self.cur_data();
+        match *cmd {
+            IncPointer => try!(self.move_ptr(1)),
+            DecPointer => try!(self.move_ptr(-1)),
+            IncData    => { let v = self.combine(cur_data, 1); self.set_cur_data(v) },
+            DecData    => { let v = self.combine(cur_data, -1); self.set_cur_data(v) },
+            GetByte    => { let b = try!(self.getbyte()); self.set_cur_data(b) },
+            PutByte    => try!(self.putbyte()),
+            DumpTape   => try!(self.dump_tape()),
+            Breakpoint => (),
+        }
+        Ok(())
+    }
+
+    /// compile `p` into `Bytecode` for this context's own `TapeConfig`
+    ///
+    /// `Program::compile`/`compile_for` need to know the pointer-wrap and
+    /// cell-overflow policy to decide which runs of ops are safe to coalesce
+    /// (see `bytecode::compile_into`); this threads the context's own
+    /// config through so the result always agrees with `execute`.
+    pub fn compile(&self, p: &Program) -> Bytecode {
+        let config = TapeConfig::new()
+            .size(self.tape_size)
+            .pointer_wrap(self.pointer_wrap)
+            .overflow(self.overflow);
+        p.compile_for(config)
+    }
+
+    /// run precompiled `bytecode` in this context
+    ///
+    /// Unlike `execute`, this walks a flat instruction stream with a single
+    /// program counter: no recursion and no re-walking of nested loops, so
+    /// prefer this over `execute` once a `Program` has been compiled.
+    ///
+    /// `bytecode` must have been compiled for this context's own
+    /// `TapeConfig` (e.g. via `Context::compile`) — bytecode compiled for a
+    /// different config may have coalesced ops in a way that's unsound here.
+    pub fn run(&mut self, bytecode: &Bytecode) -> Result<(), String> {
+        let instrs = bytecode.instrs();
+        let mut pc = 0;
+        while pc < instrs.len() {
+            match instrs[pc] {
+                Instr::AddData(n) => {
+                    let cur = self.cur_data();
+                    let v = self.combine(cur, n);
+                    self.set_cur_data(v);
+                },
+                Instr::MovePtr(n) => try!(self.move_ptr(n)),
+                Instr::SetZero => self.set_cur_data(0),
+                Instr::GetByte => { let b = try!(self.getbyte()); self.set_cur_data(b) },
+                Instr::PutByte => try!(self.putbyte()),
+                Instr::DumpTape => try!(self.dump_tape()),
+                Instr::Breakpoint => (),
+                Instr::JumpIfZero(target) => {
+                    if self.cur_data() == 0 { pc = target; continue }
+                },
+                Instr::JumpIfNonZero(target) => {
+                    if self.cur_data() != 0 { pc = target; continue }
+                },
+            }
+            pc += 1;
+        }
+        Ok(())
+    }
+
+    /// move the data pointer by `delta` cells, applying the configured
+    /// `PointerWrap` mode if the tape is `TapeSize::Fixed`
+    fn move_ptr(&mut self, delta: isize) -> Result<(), String> {
+        match self.tape_size {
+            TapeSize::Unbounded => {
+                self.dp = if delta >= 0 {
+                    self.dp.wrapping_add(delta as usize)
+                } else {
+                    self.dp.wrapping_sub((-delta) as usize)
+                };
+                Ok(())
+            },
+            TapeSize::Fixed(0) => {
+                Err("data pointer moved out of bounds: tape has 0 cells".to_string())
+            },
+            TapeSize::Fixed(n) => {
+                let moved = self.dp as isize + delta;
+                if moved >= 0 && (moved as usize) < n {
+                    self.dp = moved as usize;
+                    return Ok(());
+                }
+                match self.pointer_wrap {
+                    PointerWrap::WrapAround => {
+                        let n = n as isize;
+                        self.dp = (((moved % n) + n) % n) as usize;
+                        Ok(())
+                    },
+                    PointerWrap::Clamp => {
+                        self.dp = if moved < 0 { 0 } else { n - 1 };
+                        Ok(())
+                    },
+                    PointerWrap::Error => {
+                        Err(format!("data pointer moved out of bounds: {} (tape has {} cells)", moved, n))
+                    },
+                }
+            },
+        }
+    }
+
+    /// add `delta` to `value`, applying the configured `CellOverflow` mode
+    fn combine(&self, value: u8, delta: i8) -> u8 {
+        let result = value as i32 + delta as i32;
+        match self.overflow {
+            CellOverflow::Wrapping => (result & 0xFF) as u8,
+            CellOverflow::Saturating => {
+                if result < 0 { 0 } else if result > 255 { 255 } else { result as u8 }
+            },
+        }
+    }
+
     /// set data cell at `address` to `value`
     ///
     /// It is preferred that you use this rather than accessing the data cell
     /// directly, as this will ensure the address is in fact allocated,
     /// preventing panics
     pub fn setdata(&mut self, address: usize, value: u8) {
-        if address >= self.data.len() {
-            let diff = address - self.data.len() + 1;
-            self.data.extend((0..diff).map(|_| 0u8));
+        if let TapeSize::Unbounded = self.tape_size {
+            if address >= self.data.len() {
+                let diff = address - self.data.len() + 1;
+                self.data.extend((0..diff).map(|_| 0u8));
 
-            // after we extend, address should be within bounds
-            assert!(address < self.data.len());
+                // after we extend, address should be within bounds
+                assert!(address < self.data.len());
+            }
         };
-        self.data[address] = value;
+        // a `TapeSize::Fixed` tape is never grown: a write past its end is
+        // dropped rather than allocated
+        if address < self.data.len() {
+            self.data[address] = value;
+        }
     }
 
     /// get data cell at `address`
@@ -87,6 +321,11 @@ impl Context {
         if address >= self.data.len() { 0 } else { self.data[address] }
     }
 
+    /// the data pointer's current address
+    pub fn pointer(&self) -> usize {
+        self.dp
+    }
+
     fn cur_data(&self) -> u8 {
         self.getdata(self.dp)
     }
@@ -96,45 +335,88 @@ impl Context {
         self.setdata(dp, value)
     }
 
-    fn getbyte() -> Result<u8, String> {
-        loop {
-            let mut input = String::new();
-            println!("enter single byte: ");
-            try!(stdin().read_line(&mut input)
-                .map_err(|e| format!("could not read char: {}", e)));
-
-            if input.len() > 1 {
-                println!("only a single char, please");
-                continue;
-            } else if (input.chars().next().unwrap() as u32) > 256 {
-                println!("char must fit in a single byte");
-                continue;
-            } else {
-                return Ok(input.as_bytes()[0]);
-            }
+    fn getbyte(&mut self) -> Result<u8, String> {
+        let mut buf = [0u8; 1];
+        let n = try!(self.input.read(&mut buf)
+            .map_err(|e| format!("could not read byte: {}", e)));
+
+        if n == 0 {
+            Ok(match self.eof_policy {
+                EofPolicy::Unchanged => self.cur_data(),
+                EofPolicy::Zero => 0,
+                EofPolicy::Max => 255,
+            })
+        } else {
+            Ok(buf[0])
         }
     }
+
+    fn putbyte(&mut self) -> Result<(), String> {
+        let byte = self.cur_data();
+        self.output.write_all(&[byte])
+            .map_err(|e| format!("could not write byte: {}", e))
+    }
+
+    /// write a `#` dump of the pointer and a small window of tape around it
+    fn dump_tape(&mut self) -> Result<(), String> {
+        const WINDOW: usize = 4;
+        let start = self.dp.saturating_sub(WINDOW);
+        let end = self.dp.saturating_add(WINDOW + 1);
+
+        let mut dump = String::new();
+        dump.push_str("# dp=");
+        dump.push_str(&self.dp.to_string());
+        dump.push_str(" tape=[");
+        for addr in start..end {
+            if addr != start { dump.push_str(", ") }
+            if addr == self.dp { dump.push('*') }
+            dump.push_str(&self.getdata(addr).to_string());
+        }
+        dump.push_str("]\n");
+
+        self.output.write_all(dump.as_bytes())
+            .map_err(|e| format!("could not write tape dump: {}", e))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Context<Stdin, Stdout> {
+    /// build a new program context wired up to stdin/stdout, as a
+    /// convenience for interactive use
+    pub fn stdio() -> Context<Stdin, Stdout> {
+        Context::new(stdin(), stdout())
+    }
 }
 
 /// Used for parsing errors
 #[derive(Debug)]
 pub struct ParseError {
-    description: String
+    description: String,
+    position: Position,
 }
 
 impl ParseError {
-    fn new(d: &str) -> ParseError {
-        ParseError { description: d.to_string() }
+    fn new(d: &str, position: Position) -> ParseError {
+        ParseError { description: d.to_string(), position: position }
+    }
+
+    /// the position in the source text the error was found at — for an
+    /// unbalanced '[', this is where that '[' was opened, not where parsing
+    /// ran out of input
+    pub fn position(&self) -> Position {
+        self.position
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for ParseError {
     fn description(&self) -> &str { &self.description }
 }
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "ParseError: {}", self.description)
+        write!(f, "ParseError at line {}, col {}: {}",
+            self.position.line, self.position.column, self.description)
     }
 }
 
@@ -145,6 +427,32 @@ enum ParseResult {
     Err(ParseError),
 }
 
+/// configures which dialect `parse` accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseConfig {
+    /// recognize the debug-only `#` (`DumpTape`) and `!` (`Breakpoint`)
+    /// commands; off by default so standard programs that happen to
+    /// contain those characters as comments are unaffected
+    pub extensions: bool,
+}
+
+impl ParseConfig {
+    pub fn new() -> ParseConfig {
+        ParseConfig { extensions: false }
+    }
+
+    pub fn extensions(mut self, enabled: bool) -> ParseConfig {
+        self.extensions = enabled;
+        self
+    }
+}
+
+impl Default for ParseConfig {
+    fn default() -> ParseConfig {
+        ParseConfig::new()
+    }
+}
+
 /// parse a brainfuck program
 ///
 /// # Failures
@@ -153,20 +461,33 @@ enum ParseResult {
 pub fn parse<T>(stream: &mut T) -> Result<Program, ParseError>
     where T: Iterator<Item=char>
 {
+    parse_with_config(stream, ParseConfig::default())
+}
+
+/// parse a brainfuck program using an explicit `ParseConfig`
+///
+/// # Failures
+/// See `parse`.
+pub fn parse_with_config<T>(stream: &mut T, config: ParseConfig) -> Result<Program, ParseError>
+    where T: Iterator<Item=char>
+{
+    let mut pos = Position::start();
     let mut program: Program = Vec::with_capacity(20);
     while let Some(c) = stream.next() {
+        let char_pos = pos;
+        pos.advance(c);
         use self::ParseResult::*;
-        match parse_char(c, stream) {
+        match parse_char(c, char_pos, stream, &mut pos, config) {
             Some(ast) => program.push(ast),
             Ignore => continue,
-            LoopEnd => return Result::Err(ParseError::new("extra ']'")),
+            LoopEnd => return Result::Err(ParseError::new("extra ']'", char_pos)),
             Err(x) => return Result::Err(x)
         }
     }
     Ok(program)
 }
 
-fn parse_char<T>(c: char, stream: &mut T) -> ParseResult
+fn parse_char<T>(c: char, pos: Position, stream: &mut T, tracker: &mut Position, config: ParseConfig) -> ParseResult
     where T: Iterator<Item=char>
 {
     use self::ParseResult::*;
@@ -177,29 +498,238 @@ fn parse_char<T>(c: char, stream: &mut T) -> ParseResult
         '-' => Some(Op(DecData)),
         ',' => Some(Op(GetByte)),
         '.' => Some(Op(PutByte)),
-        '[' => parse_loop(stream),
+        '#' if config.extensions => Some(Op(DumpTape)),
+        '!' if config.extensions => Some(Op(Breakpoint)),
+        '[' => parse_loop(pos, stream, tracker, config),
         ']' => LoopEnd,
         _   => Ignore,
     }
 }
 
-fn parse_loop<T>(stream: &mut T) -> ParseResult
+fn parse_loop<T>(open_pos: Position, stream: &mut T, tracker: &mut Position, config: ParseConfig) -> ParseResult
     where T: Iterator<Item=char>
 {
     let mut commands: Program = Vec::with_capacity(20);
     loop {
         if let Some(c) = stream.next() {
+            let char_pos = *tracker;
+            tracker.advance(c);
             use self::ParseResult::*;
-            match parse_char(c, stream) {
+            match parse_char(c, char_pos, stream, tracker, config) {
                 Some(ast) => commands.push(ast),
                 Ignore => continue,
                 LoopEnd => break,
                 x => return x,
             }
         } else {
-            let err = ParseError::new("Missing ']' character");
+            let err = ParseError::new("Missing ']' character", open_pos);
             return ParseResult::Err(err);
         }
     }
     ParseResult::Some(Loop(commands))
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn parse_ops(s: &str) -> Program {
+        parse(&mut s.chars()).unwrap()
+    }
+
+    /// an in-memory output sink that's still readable after being moved into
+    /// a `Context`, for asserting on `PutByte` output in tests
+    #[derive(Clone)]
+    struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+    impl SharedBuf {
+        fn new() -> SharedBuf {
+            SharedBuf(Rc::new(RefCell::new(Vec::new())))
+        }
+
+        fn contents(&self) -> Vec<u8> {
+            self.0.borrow().clone()
+        }
+    }
+
+    impl std::io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // regression test for the `combine` coalescing bug: `execute` applies
+    // "++--" one op at a time and clamps at 255 in between, `run` used to
+    // coalesce the whole run into a single AddData(0) and miss the clamp
+    #[test]
+    fn execute_and_run_agree_under_saturating_overflow() {
+        let config = TapeConfig::new().overflow(CellOverflow::Saturating);
+        let program = parse_ops("++--");
+
+        let mut exec_ctx = Context::with_config(&b""[..], Vec::new(), EofPolicy::Zero, config);
+        exec_ctx.setdata(0, 254);
+        exec_ctx.execute(&program).unwrap();
+
+        let mut run_ctx = Context::with_config(&b""[..], Vec::new(), EofPolicy::Zero, config);
+        run_ctx.setdata(0, 254);
+        let bytecode = run_ctx.compile(&program);
+        run_ctx.run(&bytecode).unwrap();
+
+        assert_eq!(exec_ctx.getdata(0), 253);
+        assert_eq!(exec_ctx.getdata(0), run_ctx.getdata(0));
+    }
+
+    // regression test for the `move_ptr` coalescing bug: `execute` errors
+    // out the moment the 5th '>' moves the pointer off a 5-cell tape,
+    // `run` used to coalesce ">>>>>>><<<" into a single MovePtr(4), which
+    // stays on the tape, and never notice the boundary was crossed
+    #[test]
+    fn execute_and_run_agree_under_pointer_wrap_error() {
+        let config = TapeConfig::new().size(TapeSize::Fixed(5)).pointer_wrap(PointerWrap::Error);
+        let program = parse_ops(">>>>>>><<<");
+
+        let mut exec_ctx = Context::with_config(&b""[..], Vec::new(), EofPolicy::Zero, config);
+        let exec_result = exec_ctx.execute(&program);
+
+        let mut run_ctx = Context::with_config(&b""[..], Vec::new(), EofPolicy::Zero, config);
+        let bytecode = run_ctx.compile(&program);
+        let run_result = run_ctx.run(&bytecode);
+
+        assert!(exec_result.is_err());
+        assert!(run_result.is_err());
+    }
+
+    #[test]
+    fn fixed_zero_tape_errors_instead_of_panicking() {
+        let config = TapeConfig::new().size(TapeSize::Fixed(0)).pointer_wrap(PointerWrap::WrapAround);
+        let mut ctx = Context::with_config(&b""[..], Vec::new(), EofPolicy::Zero, config);
+        assert!(ctx.execute(&parse_ops(">")).is_err());
+
+        let config = TapeConfig::new().size(TapeSize::Fixed(0)).pointer_wrap(PointerWrap::Clamp);
+        let mut ctx = Context::with_config(&b""[..], Vec::new(), EofPolicy::Zero, config);
+        assert!(ctx.execute(&parse_ops(">")).is_err());
+    }
+
+    #[test]
+    fn eof_policy_governs_get_byte_past_input_end() {
+        let mut ctx = Context::with_eof_policy(&b""[..], Vec::new(), EofPolicy::Max);
+        ctx.execute(&parse_ops(",")).unwrap();
+        assert_eq!(ctx.getdata(0), 255);
+
+        let mut ctx = Context::with_eof_policy(&b""[..], Vec::new(), EofPolicy::Zero);
+        ctx.setdata(0, 42);
+        ctx.execute(&parse_ops(",")).unwrap();
+        assert_eq!(ctx.getdata(0), 0);
+
+        let mut ctx = Context::with_eof_policy(&b""[..], Vec::new(), EofPolicy::Unchanged);
+        ctx.setdata(0, 42);
+        ctx.execute(&parse_ops(",")).unwrap();
+        assert_eq!(ctx.getdata(0), 42);
+    }
+
+    // this request's own justification for the injectable Read/Write was
+    // capturing output into a Vec<u8> and asserting on it in tests
+    #[test]
+    fn put_byte_writes_to_the_output_sink() {
+        let output = SharedBuf::new();
+        let mut ctx = Context::with_eof_policy(&b""[..], output.clone(), EofPolicy::Zero);
+        ctx.setdata(0, 65);
+        ctx.execute(&parse_ops(".+.")).unwrap();
+        assert_eq!(output.contents(), vec![65, 66]);
+    }
+
+    #[test]
+    fn compiled_bytecode_runs_the_zeroing_idiom() {
+        let program = parse_ops("+++++[-]");
+        let mut ctx = Context::with_eof_policy(&b""[..], Vec::new(), EofPolicy::Zero);
+        let bytecode = ctx.compile(&program);
+        assert!(bytecode.instrs().contains(&Instr::SetZero));
+        ctx.run(&bytecode).unwrap();
+        assert_eq!(ctx.getdata(0), 0);
+    }
+
+    // classic nested-loop multiplication (3 * 2), exercising JumpIfZero/
+    // JumpIfNonZero and loop coalescing together, with observable PutByte
+    // output through compile()+run() rather than execute()
+    #[test]
+    fn compiled_bytecode_runs_a_nested_loop_to_completion() {
+        let program = parse_ops("+++[>++[>+<-]<-]>>.");
+        let output = SharedBuf::new();
+        let mut ctx = Context::with_eof_policy(&b""[..], output.clone(), EofPolicy::Zero);
+        let bytecode = ctx.compile(&program);
+        ctx.run(&bytecode).unwrap();
+        assert_eq!(output.contents(), vec![6]);
+    }
+
+    #[test]
+    fn parse_error_on_missing_close_bracket_points_at_the_open() {
+        let err = parse(&mut "ab[cd".chars()).unwrap_err();
+        assert_eq!(err.position().line, 1);
+        assert_eq!(err.position().column, 3);
+    }
+
+    #[test]
+    fn parse_error_on_extra_close_bracket_points_at_the_bracket() {
+        let err = parse(&mut "ab]cd".chars()).unwrap_err();
+        assert_eq!(err.position().line, 1);
+        assert_eq!(err.position().column, 3);
+    }
+
+    // regression test: dp wraps to usize::MAX on an Unbounded tape via
+    // move_ptr's wrapping_sub, and dump_tape used to panic computing
+    // `self.dp + WINDOW + 1` with a raw `+`
+    #[test]
+    fn dump_tape_does_not_overflow_at_max_pointer() {
+        let extensions = ParseConfig::new().extensions(true);
+        let program = parse_with_config(&mut "<#".chars(), extensions).unwrap();
+        let mut ctx = Context::with_eof_policy(&b""[..], Vec::new(), EofPolicy::Zero);
+        ctx.execute(&program).unwrap();
+    }
+}
+
+/// exercises the `io_nostd` `Read`/`Write` shim, so it's only compiled (and
+/// only makes sense to run) when the `std` feature is off
+#[cfg(all(test, not(feature = "std")))]
+mod nostd_tests {
+    use super::*;
+
+    struct FixedReader {
+        data: &'static [u8],
+        pos: usize,
+    }
+
+    impl Read for FixedReader {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, &'static str> {
+            if self.pos >= self.data.len() {
+                return Ok(0);
+            }
+            buf[0] = self.data[self.pos];
+            self.pos += 1;
+            Ok(1)
+        }
+    }
+
+    struct NullWriter;
+
+    impl Write for NullWriter {
+        fn write_all(&mut self, _buf: &[u8]) -> Result<(), &'static str> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn executes_through_the_io_nostd_read_write_shim() {
+        let reader = FixedReader { data: b"A", pos: 0 };
+        let mut ctx = Context::new(reader, NullWriter);
+        let program = parse(&mut ",.".chars()).unwrap();
+        ctx.execute(&program).unwrap();
+        assert_eq!(ctx.getdata(0), b'A');
+    }
+}
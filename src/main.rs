@@ -1,3 +1,6 @@
+// this crate targets edition 2015 and uses `try!` throughout on purpose
+#![allow(deprecated)]
+
 extern crate brainfsk;
 extern crate docopt;
 
@@ -5,13 +8,16 @@ use std::io;
 use std::io::Read;
 use std::fs::File;
 
-const USAGE: &'static str = "
+const USAGE: &str = "
 Usage: brainfsk [options] [--] <filename>
        brainfsk (-h | --help)
 
 Options:
-    -d, --dump  Instead of executing, dump string representation of ops to stdout
-    -h, --help  Show this message
+    -d, --dump        Instead of executing, dump string representation of ops to stdout
+    -t, --trace       Print each executed op and tape state as the program runs
+    -s, --step        Like --trace, but wait for Enter after each op
+    -x, --extensions  Recognize the '#' (dump tape) and '!' (breakpoint) debug commands
+    -h, --help        Show this message
 ";
 
 fn read_program(mut f: File) -> io::Result<String> {
@@ -20,8 +26,9 @@ fn read_program(mut f: File) -> io::Result<String> {
     Ok(program)
 }
 
-fn parse(s: String) -> Result<brainfsk::Program, String> {
-    brainfsk::parse(&mut s.chars())
+fn parse(s: String, extensions: bool) -> Result<brainfsk::Program, String> {
+    let config = brainfsk::ParseConfig::new().extensions(extensions);
+    brainfsk::parse_with_config(&mut s.chars(), config)
         .map_err(|e| format!("error while parsing: {}", e))
 }
 
@@ -36,7 +43,7 @@ fn dump_tokens(p: &brainfsk::Program) {
         match *a {
             brainfsk::Op(ref x) =>
                 println!("{}{:?}", indent(depth), x),
-            brainfsk::Loop(ref x) if x.len() == 0 =>
+            brainfsk::Loop(ref x) if x.is_empty() =>
                 println!("{}Loop()", indent(depth)),
             brainfsk::Loop(ref x) => {
                 print!("{}Loop(", indent(depth));
@@ -59,9 +66,24 @@ fn dump_tokens(p: &brainfsk::Program) {
 fn process_program(p: brainfsk::Program, args: docopt::ArgvMap) {
     if args.get_bool("--dump") {
         dump_tokens(&p)
+    } else if args.get_bool("--trace") || args.get_bool("--step") {
+        let step = args.get_bool("--step");
+        let mut ctx = brainfsk::Context::stdio();
+        let result = ctx.execute_traced(&p, &mut |cmd, ctx: &brainfsk::Context<_, _>| {
+            println!("{:?} dp={} cell={}", cmd, ctx.pointer(), ctx.getdata(ctx.pointer()));
+            if step {
+                let mut line = String::new();
+                let _ = io::stdin().read_line(&mut line);
+            }
+        });
+        match result {
+            Ok(_) => (),
+            Err(x) => println!("error during execution: {}", x),
+        };
     } else {
-        let mut ctx = brainfsk::Context::new();
-        match ctx.execute(&p) {
+        let mut ctx = brainfsk::Context::stdio();
+        let bytecode = ctx.compile(&p);
+        match ctx.run(&bytecode) {
             Ok(_) => (),
             Err(x) => println!("error during execution: {}", x),
         };
@@ -74,10 +96,11 @@ fn main() {
         .unwrap_or_else(|e| e.exit());
 
     let filename = args.get_str("<filename>").to_string();
+    let extensions = args.get_bool("--extensions");
     File::open(&filename)
         .and_then(read_program)
         .map_err(|e| format!("error reading {}: {}", filename, e))
-        .and_then(parse)
+        .and_then(|s| parse(s, extensions))
         .map(|p| process_program(p, args))
         .unwrap_or_else(|e| println!("brainfsk: {}", e));
 }
@@ -0,0 +1,34 @@
+//! Source positions tracked while parsing
+//!
+//! `parse` threads a `Position` through `parse_char`/`parse_loop` as it
+//! consumes characters, so a `ParseError` can point at the offending
+//! bracket instead of just naming the problem.
+
+/// A position within the source text being parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// byte offset from the start of the stream
+    pub offset: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number, reset to 1 after every `\n`
+    pub column: usize,
+}
+
+impl Position {
+    /// the position of the very first character of a stream
+    pub fn start() -> Position {
+        Position { offset: 0, line: 1, column: 1 }
+    }
+
+    /// advance past `c`, which was just consumed at this position
+    pub fn advance(&mut self, c: char) {
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Configuration for `Context`'s data tape
+//!
+//! `TapeConfig` lets callers opt into a fixed-size tape with an explicit
+//! policy for what happens when the pointer runs off either end, and a
+//! saturating alternative to wrapping cell arithmetic. The default matches
+//! the original behaviour: unbounded, wrapping pointer and cell arithmetic.
+
+/// How many cells the tape has
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapeSize {
+    /// grow on demand, as today
+    Unbounded,
+    /// a fixed number of cells, allocated up front
+    Fixed(usize),
+}
+
+/// What happens when the data pointer moves past either end of a
+/// `TapeSize::Fixed` tape
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerWrap {
+    /// wrap around to the opposite end, so `<` at cell 0 jumps to the last
+    /// cell and `>` past the end jumps back to 0
+    WrapAround,
+    /// clamp to the nearest valid cell
+    Clamp,
+    /// report it as an execution error instead of moving the pointer
+    Error,
+}
+
+/// Overflow behaviour for cell values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellOverflow {
+    /// wrap around on overflow/underflow, as today
+    Wrapping,
+    /// clamp to 0 or 255 on underflow/overflow
+    Saturating,
+}
+
+/// Builder for the tape's size, pointer-wrap mode and overflow behaviour,
+/// consumed by `Context::with_config`
+#[derive(Debug, Clone, Copy)]
+pub struct TapeConfig {
+    pub(crate) size: TapeSize,
+    pub(crate) pointer_wrap: PointerWrap,
+    pub(crate) overflow: CellOverflow,
+}
+
+impl TapeConfig {
+    /// a config matching the original behaviour: unbounded, wrapping pointer
+    /// arithmetic, wrapping cell arithmetic
+    pub fn new() -> TapeConfig {
+        TapeConfig {
+            size: TapeSize::Unbounded,
+            pointer_wrap: PointerWrap::WrapAround,
+            overflow: CellOverflow::Wrapping,
+        }
+    }
+
+    /// set the tape size
+    pub fn size(mut self, size: TapeSize) -> TapeConfig {
+        self.size = size;
+        self
+    }
+
+    /// set the pointer-wrap mode; only takes effect with `TapeSize::Fixed`
+    pub fn pointer_wrap(mut self, pointer_wrap: PointerWrap) -> TapeConfig {
+        self.pointer_wrap = pointer_wrap;
+        self
+    }
+
+    /// set the cell overflow behaviour
+    pub fn overflow(mut self, overflow: CellOverflow) -> TapeConfig {
+        self.overflow = overflow;
+        self
+    }
+}
+
+impl Default for TapeConfig {
+    fn default() -> TapeConfig {
+        TapeConfig::new()
+    }
+}